@@ -1,7 +1,13 @@
 #[cfg(feature = "mpi")]
 pub mod mpi;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod sync;
 pub mod tiled_display;
 
 #[cfg(feature = "mpi")]
 pub use mpi::*;
+#[cfg(feature = "record")]
+pub use record::*;
+pub use sync::{SyncBackend, SyncBackends};
 pub use tiled_display::*;
@@ -0,0 +1,301 @@
+//! Per-tile AV1 recording, so a whole multi-machine wall session can be
+//! captured and later recombined for synchronized playback. Add
+//! [`RecordPlugin`] to your `App` alongside `TiledDisplayPlugin`.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use bevy::{
+    prelude::*,
+    render::view::screenshot::{Screenshot, ScreenshotCaptured},
+};
+use rav1e::prelude::*;
+
+use crate::tiled_display::{MachineIdentity, StereoChannel, Tile};
+
+/// Frames queued between the capture systems and the encoder thread. Once
+/// full, a frame is dropped rather than stalling the render loop.
+const FRAME_QUEUE_DEPTH: usize = 4;
+
+/// Captures this rank's tile framebuffer every frame and encodes it to a
+/// per-tile `.ivf` (AV1) stream plus a sidecar manifest, so a post-process
+/// step can lay every rank's stream back into the full `TiledDisplay`
+/// geometry for synchronized playback.
+pub struct RecordPlugin;
+
+impl Plugin for RecordPlugin {
+    fn build(&self, app: &mut App) {
+        // `Screenshot::primary_window()` must be spawned in the main world
+        // (it targets a main-world window entity), so it can't live in the
+        // render world's `ExtractSchedule` itself. `Last` is the closest
+        // equivalent: it's the final main-world stage for this frame,
+        // running immediately before that frame is handed off to the render
+        // world for extraction/rendering.
+        app.add_systems(Startup, record_start_system)
+            .add_systems(Last, record_frame_system);
+    }
+}
+
+/// Tags the `Screenshot` entity with the wall-clock frame index it was
+/// requested on, so the recorded stream can carry real per-frame sequence
+/// numbers even if some frames are later dropped under backpressure.
+#[derive(Component)]
+struct RecordFrameIndex(u64);
+
+/// Bridges the capture systems with the background encoder thread.
+#[derive(Resource)]
+struct RecordContext {
+    frame_tx: Option<mpsc::SyncSender<(u64, Vec<u8>)>>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for RecordContext {
+    fn drop(&mut self) {
+        // Dropping the sender closes the channel, so the encoder thread's
+        // `recv` loop ends and it flushes/finalizes the stream; join so that
+        // finishes before the process exits. Mirrors how `MpiContext`
+        // finalizes MPI on drop.
+        self.frame_tx.take();
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+fn record_start_system(mut commands: Commands, tile: Res<Tile>, identity: Res<MachineIdentity>) {
+    let size = tile.size();
+    let path = PathBuf::from(format!("record_{}.ivf", tile.name));
+    write_manifest(&tile, &identity, &path);
+
+    let (frame_tx, frame_rx) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+    let worker = thread::spawn(move || encode_worker(path, size, frame_rx));
+
+    commands.insert_resource(RecordContext {
+        frame_tx: Some(frame_tx),
+        worker: Some(worker),
+    });
+}
+
+fn record_frame_system(
+    ctx: Option<Res<RecordContext>>,
+    mut frame_counter: Local<u64>,
+    mut commands: Commands,
+) {
+    if ctx.is_some() {
+        let frame_no = *frame_counter;
+        *frame_counter += 1;
+        commands
+            .spawn((Screenshot::primary_window(), RecordFrameIndex(frame_no)))
+            .observe(record_captured_system);
+    }
+}
+
+fn record_captured_system(
+    trigger: Trigger<ScreenshotCaptured>,
+    ctx: Res<RecordContext>,
+    frame_indices: Query<&RecordFrameIndex>,
+) {
+    let Some(pixels) = trigger.event().0.data.clone() else {
+        return;
+    };
+    let frame_no = frame_indices
+        .get(trigger.target())
+        .map(|index| index.0)
+        .unwrap_or(0);
+    if let Some(frame_tx) = &ctx.frame_tx {
+        // Drop the frame rather than block the render loop if the encoder
+        // thread is behind; `frame_no` still lets the post-process step see
+        // exactly which frames were dropped and realign streams around them.
+        let _ = frame_tx.try_send((frame_no, pixels));
+    }
+}
+
+fn write_manifest(tile: &Tile, identity: &MachineIdentity, stream_path: &Path) {
+    let offset = tile.offset();
+    let size = tile.size();
+    let stereo_channel = match tile.stereo_channel {
+        StereoChannel::Left => "Left",
+        StereoChannel::Right => "Right",
+    };
+    let manifest = format!(
+        "{{\"name\":\"{}\",\"identity\":\"{}\",\"stream\":\"{}\",\"offset\":[{},{}],\"size\":[{},{}],\"stereo_channel\":\"{}\"}}",
+        tile.name,
+        identity.0,
+        stream_path.display(),
+        offset.x,
+        offset.y,
+        size.x,
+        size.y,
+        stereo_channel,
+    );
+    if let Err(error) = std::fs::write(format!("record_{}.json", tile.name), manifest) {
+        error!(error = %error, "Failed to write recording manifest");
+    }
+}
+
+/// Owns the AV1 encoder and `.ivf` muxing for one tile's stream.
+fn encode_worker(path: PathBuf, size: UVec2, frame_rx: mpsc::Receiver<(u64, Vec<u8>)>) {
+    let mut encoder = match Av1Encoder::new(&path, size.x, size.y) {
+        Ok(encoder) => encoder,
+        Err(error) => {
+            error!(error = %error, "Failed to start AV1 encoder");
+            return;
+        }
+    };
+
+    while let Ok((frame_no, rgba)) = frame_rx.recv() {
+        let yuv = rgba_to_yuv420(&rgba, size.x as usize, size.y as usize);
+        if let Err(error) = encoder.encode_frame(&yuv, frame_no) {
+            error!(error = %error, "Failed to encode recording frame");
+            break;
+        }
+    }
+
+    if let Err(error) = encoder.finish() {
+        error!(error = %error, "Failed to finalize recording");
+    }
+}
+
+struct Av1Encoder {
+    file: std::fs::File,
+    ctx: Context<u8>,
+    /// Wall-clock frame indices for frames submitted but not yet emitted as
+    /// packets, in submission order, so each output packet can be stamped
+    /// with the original frame index it corresponds to (rav1e may buffer a
+    /// few frames of lookahead before emitting packets).
+    pending_frame_numbers: VecDeque<u64>,
+}
+
+impl Av1Encoder {
+    fn new(path: &Path, width: u32, height: u32) -> std::io::Result<Self> {
+        let enc = EncoderConfig {
+            width: width as usize,
+            height: height as usize,
+            speed_settings: SpeedSettings::from_preset(8),
+            ..Default::default()
+        };
+        let cfg = Config::new().with_encoder_config(enc);
+        let ctx = cfg
+            .new_context()
+            .map_err(|error| std::io::Error::other(error.to_string()))?;
+
+        let mut file = std::fs::File::create(path)?;
+        write_ivf_header(&mut file, width as u16, height as u16)?;
+        Ok(Self {
+            file,
+            ctx,
+            pending_frame_numbers: VecDeque::new(),
+        })
+    }
+
+    fn encode_frame(&mut self, yuv: &Yuv420, frame_no: u64) -> std::io::Result<()> {
+        let mut frame = self.ctx.new_frame();
+        frame.planes[0].copy_from_raw_u8(&yuv.y, yuv.width, 1);
+        frame.planes[1].copy_from_raw_u8(&yuv.u, yuv.width / 2, 1);
+        frame.planes[2].copy_from_raw_u8(&yuv.v, yuv.width / 2, 1);
+
+        self.ctx
+            .send_frame(frame)
+            .map_err(|error| std::io::Error::other(error.to_string()))?;
+        self.pending_frame_numbers.push_back(frame_no);
+        self.drain()
+    }
+
+    fn drain(&mut self) -> std::io::Result<()> {
+        loop {
+            match self.ctx.receive_packet() {
+                Ok(packet) => self.write_packet(&packet.data)?,
+                Err(EncoderStatus::Encoded) | Err(EncoderStatus::NeedMoreData) => break,
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_packet(&mut self, data: &[u8]) -> std::io::Result<()> {
+        // Every `send_frame` pushed exactly one entry, in order, so the
+        // front of the queue is always this packet's original frame index.
+        let frame_no = self.pending_frame_numbers.pop_front().unwrap_or(0);
+        write_ivf_frame(&mut self.file, frame_no, data)
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.ctx.flush();
+        loop {
+            match self.ctx.receive_packet() {
+                Ok(packet) => self.write_packet(&packet.data)?,
+                Err(EncoderStatus::LimitReached) => break,
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Minimal IVF container: a 32-byte file header followed by one 12-byte
+/// frame header + payload per packet.
+///
+/// Frames are captured on-demand and can be dropped under backpressure, so
+/// there is no fixed frame rate to declare. The timebase is instead set to
+/// 1/1, making each frame header's timestamp field hold the *original*
+/// wall-clock frame index (see `RecordFrameIndex`) rather than a duration —
+/// a post-process step can use it directly to detect gaps from dropped
+/// frames and realign this rank's stream against the others.
+fn write_ivf_header(file: &mut std::fs::File, width: u16, height: u16) -> std::io::Result<()> {
+    let mut header = [0u8; 32];
+    header[0..4].copy_from_slice(b"DKIF");
+    header[6..8].copy_from_slice(&32u16.to_le_bytes());
+    header[8..12].copy_from_slice(b"AV01");
+    header[12..14].copy_from_slice(&width.to_le_bytes());
+    header[14..16].copy_from_slice(&height.to_le_bytes());
+    header[16..20].copy_from_slice(&1u32.to_le_bytes());
+    header[20..24].copy_from_slice(&1u32.to_le_bytes());
+    file.write_all(&header)
+}
+
+fn write_ivf_frame(file: &mut std::fs::File, frame_no: u64, data: &[u8]) -> std::io::Result<()> {
+    let mut header = [0u8; 12];
+    header[0..4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+    header[4..12].copy_from_slice(&frame_no.to_le_bytes());
+    file.write_all(&header)?;
+    file.write_all(data)
+}
+
+/// Planar YUV 4:2:0 buffer, BT.601 full range.
+struct Yuv420 {
+    width: usize,
+    y: Vec<u8>,
+    u: Vec<u8>,
+    v: Vec<u8>,
+}
+
+fn rgba_to_yuv420(rgba: &[u8], width: usize, height: usize) -> Yuv420 {
+    let mut y = vec![0u8; width * height];
+    let mut u = vec![0u8; width.div_ceil(2) * height.div_ceil(2)];
+    let mut v = vec![0u8; width.div_ceil(2) * height.div_ceil(2)];
+
+    for row in 0..height {
+        for col in 0..width {
+            let pixel = (row * width + col) * 4;
+            let (r, g, b) = (
+                rgba[pixel] as f32,
+                rgba[pixel + 1] as f32,
+                rgba[pixel + 2] as f32,
+            );
+            y[row * width + col] = (0.299 * r + 0.587 * g + 0.114 * b) as u8;
+
+            // Subsample chroma by only writing on even rows/columns.
+            if row % 2 == 0 && col % 2 == 0 {
+                let chroma_width = width.div_ceil(2);
+                let chroma_index = (row / 2) * chroma_width + col / 2;
+                u[chroma_index] = (128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b) as u8;
+                v[chroma_index] = (128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b) as u8;
+            }
+        }
+    }
+
+    Yuv420 { width, y, u, v }
+}
@@ -1,10 +1,16 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bevy::app::SubApp;
 use bevy::prelude::*;
 
 #[cfg(feature = "mpi")]
 pub mod mpi;
+pub mod network;
 
 #[cfg(feature = "mpi")]
 pub use mpi::*;
+pub use network::*;
 
 /// Trait for screen synchronization backends.
 ///
@@ -13,14 +19,55 @@ pub use mpi::*;
 #[allow(dead_code)]
 pub trait SyncBackend {
     /// Called during app construction to register resources and systems.
+    ///
+    /// This is the "render-complete" fence: it runs in the main world's
+    /// `Last` schedule, after every rank has finished simulating and
+    /// recording its frame, but before any rank has presented it.
     fn setup(&self, app: &mut App);
+
+    /// Called during app construction, if the plugin's `present_barrier` is
+    /// enabled, to register a second fence inside the render world.
+    ///
+    /// This "present" fence runs after `setup`'s barrier, just before
+    /// surface presentation, so every rank swaps its backbuffer in the same
+    /// short window instead of merely finishing rendering in sync. Default
+    /// implementation is a no-op for backends that don't support it.
+    fn setup_present_barrier(&self, _render_app: &mut SubApp) {}
+}
+
+/// Shared between the main world and, when `present_barrier` is enabled, the
+/// render world, so a present-fence timeout can still bring the app down
+/// gracefully even though the render world's schedule isn't polled by the
+/// runner's `AppExit` check.
+///
+/// The render-world barrier system can only `mark()` this flag on failure;
+/// the next main-world barrier system to run (every `Last` schedule, at most
+/// one frame later) notices the mark and sends `AppExit` itself. Routing the
+/// exit through `AppExit` instead of `std::process::exit` lets `App::run()`
+/// return normally, so every resource it owns -- `RecordContext`, `MpiContext`
+/// -- is dropped and finalized, instead of the process being torn down with
+/// no destructors run at all.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct SyncFailed(Arc<AtomicBool>);
+
+impl SyncFailed {
+    pub(crate) fn mark(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_marked(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
 }
 
 /// Simple selection enum for available synchronization backends.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SyncBackends {
     /// Pick a sensible default at runtime or by feature flags.
     Auto,
     /// Use an MPI-backed barrier synchronization (requires `mpi` feature).
     Mpi,
+    /// Use a TCP-backed barrier synchronization for machines not launched
+    /// under an MPI runtime.
+    Network(NetworkSync),
 }
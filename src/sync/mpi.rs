@@ -1,5 +1,7 @@
-use super::SyncBackend;
+use super::{SyncBackend, SyncFailed};
+use bevy::app::SubApp;
 use bevy::prelude::*;
+use bevy::render::{renderer::render_system, Render, RenderSet};
 use mpi::environment::Universe;
 use mpi::request::Request;
 use mpi::topology::SimpleCommunicator;
@@ -28,6 +30,19 @@ impl SyncBackend for MpiSync {
 
         info!("Rank {} initialized (size {})", world.rank(), world.size());
     }
+
+    fn setup_present_barrier(&self, render_app: &mut SubApp) {
+        // The render world doesn't share the main world's NonSend resources,
+        // but MPI was already initialized by `setup` above (which keeps the
+        // `Universe` alive in the main world), so it's safe to just grab a
+        // fresh handle to COMM_WORLD here.
+        render_app.add_systems(
+            Render,
+            mpi_present_barrier_system
+                .in_set(RenderSet::Render)
+                .before(render_system),
+        );
+    }
 }
 
 fn get_world(universe: &Option<Universe>) -> SimpleCommunicator {
@@ -54,11 +69,45 @@ fn busy_barrier(world: &impl Communicator, timeout: Duration) -> bool {
     }
 }
 
-/// Blocks at the end of a frame until all MPI ranks reach this point.
-fn mpi_frame_barrier_system(ctx: NonSend<MpiContext>) {
+/// Blocks at the end of a frame until all MPI ranks have finished rendering.
+/// This is the first of the two genlock fences; see `mpi_present_barrier_system`
+/// for the second.
+///
+/// Failure is reported via `AppExit` rather than `std::process::exit`, so
+/// `App::run()` returns and every resource (e.g. `MpiContext`, `RecordContext`)
+/// is dropped and finalized instead of the process being torn down mid-frame.
+/// Also checks `SyncFailed`, which the present fence below marks on its own
+/// timeout since it can't post `AppExit` from inside the render world.
+fn mpi_frame_barrier_system(
+    ctx: NonSend<MpiContext>,
+    failed: Res<SyncFailed>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if failed.is_marked() {
+        exit.write(AppExit::error());
+        return;
+    }
     let world = get_world(&ctx.universe);
     if !busy_barrier(&world, Duration::from_millis(200)) {
         error!("Barrier failed or timed out. Exiting.");
-        std::process::exit(1);
+        failed.mark();
+        exit.write(AppExit::error());
+    }
+}
+
+/// Blocks inside the render world, just before presentation, until all MPI
+/// ranks reach this point, so every rank swaps its backbuffer in the same
+/// short window. Runs after `mpi_frame_barrier_system`'s fence for the
+/// previous stage of the same frame.
+///
+/// The render world's schedule isn't polled by the runner's `AppExit` check,
+/// so on failure this can only mark `SyncFailed`; `mpi_frame_barrier_system`
+/// turns that into an actual `AppExit` on its next run, at most one frame
+/// later.
+fn mpi_present_barrier_system(failed: Res<SyncFailed>) {
+    let world = SimpleCommunicator::world();
+    if !busy_barrier(&world, Duration::from_millis(200)) {
+        error!("Present barrier failed or timed out. Exiting.");
+        failed.mark();
     }
 }
@@ -0,0 +1,339 @@
+use super::{SyncBackend, SyncFailed};
+use bevy::app::SubApp;
+use bevy::prelude::*;
+use bevy::render::{renderer::render_system, Render, RenderSet};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// One-byte protocol: peers send `READY` each frame, the leader replies `GO`
+/// once every peer (and itself) is ready.
+const READY: u8 = 1;
+const GO: u8 = 2;
+
+/// TCP-based frame synchronization for walls whose processes aren't launched
+/// under an MPI runtime (e.g. started individually via SSH or a process
+/// manager). One process acts as leader and gates every other process on a
+/// one-byte ready/go handshake each frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkSync {
+    /// Address this process listens on when it is the leader. A peer only
+    /// uses it to find its own rank in `peers`; its connection to the leader
+    /// is made from an OS-chosen local address.
+    pub bind: SocketAddr,
+    /// Every peer's address, including this process's own `bind`. The first
+    /// entry is the leader unless `leader` overrides it.
+    pub peers: Vec<SocketAddr>,
+    /// Explicit leader override; defaults to `peers[0]`.
+    pub leader: Option<SocketAddr>,
+}
+
+/// Bridges the `Last`-schedule barrier system with the background socket
+/// thread that owns the actual connections.
+pub(crate) struct NetworkContext {
+    /// Signals the worker thread that this frame's barrier has been reached.
+    frame_tx: mpsc::Sender<()>,
+    /// Receives confirmation that every peer also reached the barrier.
+    go_rx: mpsc::Receiver<()>,
+}
+
+impl SyncBackend for NetworkSync {
+    fn setup(&self, app: &mut App) {
+        let rank = self.rank();
+        let size = self.peers.len();
+
+        app.insert_non_send_resource(self.spawn())
+            .add_systems(Last, network_frame_barrier_system);
+
+        info!("Rank {} initialized (size {})", rank, size);
+    }
+
+    fn setup_present_barrier(&self, render_app: &mut SubApp) {
+        // Run the present fence's ready/go handshake over its own port pair
+        // so its traffic can't interleave with the render-complete fence's.
+        let present = self.bump_ports();
+        render_app
+            .insert_non_send_resource(present.spawn())
+            .add_systems(
+                Render,
+                network_present_barrier_system
+                    .in_set(RenderSet::Render)
+                    .before(render_system),
+            );
+    }
+}
+
+impl NetworkSync {
+    fn leader(&self) -> SocketAddr {
+        self.leader.unwrap_or(self.peers[0])
+    }
+
+    fn is_leader(&self) -> bool {
+        self.leader() == self.bind
+    }
+
+    fn rank(&self) -> usize {
+        match self.peers.iter().position(|p| *p == self.bind) {
+            Some(rank) => rank,
+            None => {
+                error!(
+                    bind = %self.bind,
+                    "bind address not found in peers; defaulting to rank 0. \
+                     This process will be misidentified as the leader unless \
+                     `peers[0]` happens to be it -- fix `bind`/`peers`."
+                );
+                0
+            }
+        }
+    }
+
+    /// Derives the config for the present-fence handshake by shifting every
+    /// address's port by one, so it runs over sockets distinct from the
+    /// render-complete fence's.
+    fn bump_ports(&self) -> NetworkSync {
+        NetworkSync {
+            bind: bump_port(self.bind),
+            peers: self.peers.iter().copied().map(bump_port).collect(),
+            leader: self.leader.map(bump_port),
+        }
+    }
+
+    /// Spawns the background worker thread and returns the context used to
+    /// drive a barrier from a Bevy system.
+    pub(crate) fn spawn(&self) -> NetworkContext {
+        let leader = self.leader();
+        let is_leader = self.is_leader();
+        let bind = self.bind;
+        let peer_count = self.peers.len();
+
+        let (frame_tx, frame_rx) = mpsc::channel();
+        let (go_tx, go_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            if is_leader {
+                run_leader(bind, peer_count, &frame_rx, &go_tx);
+            } else {
+                run_peer(leader, &frame_rx, &go_tx);
+            }
+        });
+
+        NetworkContext { frame_tx, go_rx }
+    }
+}
+
+/// Render-complete fence: registered in the main world's `Last` schedule.
+///
+/// Failure is reported via `AppExit` rather than `std::process::exit`, so
+/// `App::run()` returns and every resource (e.g. `RecordContext`) is dropped
+/// and finalized instead of the process being torn down mid-frame. Also
+/// checks `SyncFailed`, which `network_present_barrier_system` marks on its
+/// own timeout since it can't post `AppExit` from inside the render world.
+pub(crate) fn network_frame_barrier_system(
+    ctx: NonSend<NetworkContext>,
+    failed: Res<SyncFailed>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if failed.is_marked() {
+        exit.write(AppExit::error());
+        return;
+    }
+    if !network_barrier_wait(&ctx) {
+        failed.mark();
+        exit.write(AppExit::error());
+    }
+}
+
+/// Present fence: registered (over a separate `NetworkContext`/port pair) in
+/// the render world, just before presentation.
+///
+/// The render world's schedule isn't polled by the runner's `AppExit` check,
+/// so on failure this can only mark `SyncFailed`; `network_frame_barrier_system`
+/// turns that into an actual `AppExit` on its next run, at most one frame
+/// later.
+pub(crate) fn network_present_barrier_system(
+    ctx: NonSend<NetworkContext>,
+    failed: Res<SyncFailed>,
+) {
+    if !network_barrier_wait(&ctx) {
+        failed.mark();
+    }
+}
+
+/// Blocks until every peer reaches this point, mirroring `mpi::busy_barrier`'s
+/// 200 ms timeout-and-exit semantics.
+fn network_barrier_wait(ctx: &NetworkContext) -> bool {
+    if ctx.frame_tx.send(()).is_err() {
+        error!("Network sync worker thread is gone. Exiting.");
+        return false;
+    }
+    if ctx.go_rx.recv_timeout(Duration::from_millis(200)).is_err() {
+        error!("Barrier failed or timed out. Exiting.");
+        return false;
+    }
+    true
+}
+
+/// Leader side: accept one long-lived connection per peer, and each frame
+/// wait for a `READY` byte from every peer before broadcasting `GO`.
+fn run_leader(
+    bind: SocketAddr,
+    size: usize,
+    frame_rx: &mpsc::Receiver<()>,
+    go_tx: &mpsc::Sender<()>,
+) {
+    let listener = TcpListener::bind(bind).expect("bind network sync leader socket");
+    listener
+        .set_nonblocking(true)
+        .expect("set leader socket non-blocking");
+    let mut peers: Vec<Option<TcpStream>> = (0..size.saturating_sub(1)).map(|_| None).collect();
+
+    while frame_rx.recv().is_ok() {
+        accept_missing_peers(&listener, &mut peers);
+
+        for peer in peers.iter_mut() {
+            if let Some(conn) = peer {
+                if read_byte(conn, READY).is_err() {
+                    warn!("Lost a peer connection while waiting for READY; will reconnect");
+                    *peer = None;
+                }
+            }
+        }
+
+        // Not every peer is connected (yet); skip broadcasting GO so this
+        // frame's barrier call times out and the caller exits, rather than
+        // silently presenting out of sync.
+        if peers.iter().any(Option::is_none) {
+            continue;
+        }
+
+        for peer in peers.iter_mut().flatten() {
+            if write_byte(peer, GO).is_err() {
+                warn!("Lost a peer connection while broadcasting GO");
+            }
+        }
+
+        if go_tx.send(()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Peer side: maintain a connection to the leader and each frame send
+/// `READY`, then block for the leader's `GO`.
+fn run_peer(leader: SocketAddr, frame_rx: &mpsc::Receiver<()>, go_tx: &mpsc::Sender<()>) {
+    let mut conn = connect_leader(leader);
+
+    while frame_rx.recv().is_ok() {
+        if write_byte(&mut conn, READY).is_err() || read_byte(&mut conn, GO).is_err() {
+            warn!("Lost connection to leader; reconnecting");
+            conn = connect_leader(leader);
+            continue;
+        }
+
+        if go_tx.send(()).is_err() {
+            return;
+        }
+    }
+}
+
+fn accept_missing_peers(listener: &TcpListener, peers: &mut [Option<TcpStream>]) {
+    for peer in peers.iter_mut() {
+        if peer.is_none() {
+            if let Ok((stream, _)) = listener.accept() {
+                stream.set_nodelay(true).ok();
+                *peer = Some(stream);
+            }
+        }
+    }
+}
+
+fn connect_leader(leader: SocketAddr) -> TcpStream {
+    loop {
+        match TcpStream::connect(leader) {
+            Ok(stream) => {
+                stream.set_nodelay(true).ok();
+                return stream;
+            }
+            Err(_) => thread::sleep(Duration::from_millis(200)),
+        }
+    }
+}
+
+fn read_byte(stream: &mut TcpStream, expected: u8) -> io::Result<()> {
+    let mut buf = [0u8; 1];
+    stream.read_exact(&mut buf)?;
+    if buf[0] != expected {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "unexpected sync byte",
+        ));
+    }
+    Ok(())
+}
+
+fn write_byte(stream: &mut TcpStream, byte: u8) -> io::Result<()> {
+    stream.write_all(&[byte])
+}
+
+fn bump_port(addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(addr.ip(), addr.port() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::new("127.0.0.1".parse().unwrap(), port)
+    }
+
+    fn sync(bind_port: u16) -> NetworkSync {
+        NetworkSync {
+            bind: addr(bind_port),
+            peers: vec![addr(9000), addr(9001), addr(9002)],
+            leader: None,
+        }
+    }
+
+    #[test]
+    fn rank_is_position_of_bind_in_peers() {
+        assert_eq!(sync(9000).rank(), 0);
+        assert_eq!(sync(9001).rank(), 1);
+        assert_eq!(sync(9002).rank(), 2);
+    }
+
+    #[test]
+    fn rank_defaults_to_zero_when_bind_is_not_a_peer() {
+        assert_eq!(sync(9999).rank(), 0);
+    }
+
+    #[test]
+    fn leader_defaults_to_first_peer() {
+        assert_eq!(sync(9000).leader(), addr(9000));
+        assert_eq!(sync(9001).leader(), addr(9000));
+    }
+
+    #[test]
+    fn leader_honors_explicit_override() {
+        let mut config = sync(9001);
+        config.leader = Some(addr(9002));
+        assert_eq!(config.leader(), addr(9002));
+        assert!(!config.is_leader());
+
+        config.bind = addr(9002);
+        assert!(config.is_leader());
+    }
+
+    #[test]
+    fn bump_ports_shifts_bind_peers_and_leader() {
+        let mut config = sync(9001);
+        config.leader = Some(addr(9000));
+
+        let bumped = config.bump_ports();
+        assert_eq!(bumped.bind, addr(9002));
+        assert_eq!(bumped.peers, vec![addr(9001), addr(9002), addr(9003)]);
+        assert_eq!(bumped.leader, Some(addr(9001)));
+    }
+}
@@ -2,13 +2,21 @@ use std::path::{Path, PathBuf};
 
 use bevy::{
     prelude::*,
-    render::camera::SubCameraView,
+    render::{
+        camera::SubCameraView,
+        view::screenshot::{Screenshot, ScreenshotCaptured},
+        RenderApp,
+    },
     window::{PrimaryWindow, WindowResolution},
 };
 use serde::Deserialize;
 
 use crate::sync::*;
 
+/// Keyboard shortcut that triggers a full-wall capture (see
+/// [`capture_trigger_system`]).
+const CAPTURE_KEY: KeyCode = KeyCode::F12;
+
 #[derive(Clone)]
 pub struct TiledDisplayPlugin {
     /// Path to the tiled display XML configuration file.
@@ -17,6 +25,11 @@ pub struct TiledDisplayPlugin {
     pub identity: String,
     /// Which synchronization backend to use for frame coordination.
     pub sync: SyncBackends,
+    /// Add a second genlock fence, inside the render world just before
+    /// presentation, so every rank swaps its backbuffer in the same short
+    /// window rather than only finishing rendering in sync. Requires the
+    /// selected `sync` backend to implement `SyncBackend::setup_present_barrier`.
+    pub present_barrier: bool,
 }
 
 #[derive(Resource, Deserialize, Debug, Clone)]
@@ -33,6 +46,48 @@ impl TiledDisplay {
     pub fn size(&self) -> UVec2 {
         UVec2::new(self.width, self.height)
     }
+
+    fn tiles(&self) -> impl Iterator<Item = &Tile> {
+        self.machines
+            .iter()
+            .flat_map(|machine| machine.tiles.iter())
+    }
+
+    /// Virtual canvas size: `size()` expanded by the accumulated bezel gaps
+    /// along each axis, i.e. the dead space behind every tile's monitor
+    /// bezels. Assumes a regular grid, so bezels are summed along the top
+    /// row (for width) and left column (for height). Identical to `size()`
+    /// when no tile declares a bezel.
+    pub fn virtual_size(&self) -> UVec2 {
+        let extra_width: i32 = self
+            .tiles()
+            .filter(|tile| tile.top_offset == 0)
+            .map(|tile| tile.bezel_left + tile.bezel_right)
+            .sum();
+        let extra_height: i32 = self
+            .tiles()
+            .filter(|tile| tile.left_offset == 0)
+            .map(|tile| tile.bezel_top + tile.bezel_bottom)
+            .sum();
+        self.size() + UVec2::new(extra_width.max(0) as u32, extra_height.max(0) as u32)
+    }
+
+    /// Virtual offset of `tile` on the `virtual_size()` canvas: its own
+    /// `offset()` plus its own left/top bezel, plus the summed bezel widths
+    /// of every tile to its left (same row) and above it (same column).
+    pub fn virtual_offset(&self, tile: &Tile) -> Vec2 {
+        let mut x = tile.bezel_left as f32;
+        let mut y = tile.bezel_top as f32;
+        for other in self.tiles() {
+            if other.top_offset == tile.top_offset && other.left_offset < tile.left_offset {
+                x += (other.bezel_left + other.bezel_right) as f32;
+            }
+            if other.left_offset == tile.left_offset && other.top_offset < tile.top_offset {
+                y += (other.bezel_top + other.bezel_bottom) as f32;
+            }
+        }
+        tile.offset() + Vec2::new(x, y)
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -61,6 +116,21 @@ pub struct Tile {
     pub window_top: i32,
     pub window_width: u32,
     pub window_height: u32,
+    /// HiDPI scale factor (device pixel ratio) of this tile's monitor.
+    /// Defaults to `1.0` when absent from the XML, so walls with no HiDPI
+    /// tiles are unaffected.
+    #[serde(default)]
+    pub scale_factor: Option<f32>,
+    /// Width (in pixels) of the physical monitor bezel on each side of this
+    /// tile. Defaults to `0`, so walls with no bezels are unaffected.
+    #[serde(default)]
+    pub bezel_left: i32,
+    #[serde(default)]
+    pub bezel_right: i32,
+    #[serde(default)]
+    pub bezel_top: i32,
+    #[serde(default)]
+    pub bezel_bottom: i32,
 }
 
 impl Tile {
@@ -70,8 +140,18 @@ impl Tile {
     pub fn size(&self) -> UVec2 {
         UVec2::new(self.window_width, self.window_height)
     }
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor.unwrap_or(1.0)
+    }
 }
 
+/// Identity of the [`Machine`] that owns this process's selected [`Tile`].
+/// `Tile` itself has no identity field (it's `Machine` that's keyed by
+/// identity in the XML), so this is inserted alongside `Tile` whenever a
+/// machine/tile is selected.
+#[derive(Resource, Debug, Clone)]
+pub struct MachineIdentity(pub String);
+
 /// Custom deserializer to convert a wrapped vector, e.g., the XML structure:
 /// <Machines>
 ///   <Machine>...</Machine>
@@ -100,6 +180,7 @@ impl Default for TiledDisplayPlugin {
             config: PathBuf::new(),
             identity: TiledDisplayPlugin::hostname(),
             sync: SyncBackends::Auto,
+            present_barrier: false,
         }
     }
 }
@@ -129,6 +210,7 @@ impl TiledDisplayPlugin {
                     None
                 }
             }
+            SyncBackends::Network(network) => Some(Box::new(network.clone())),
         }
     }
 
@@ -183,16 +265,29 @@ impl Plugin for TiledDisplayPlugin {
     fn build(&self, app: &mut App) {
         let tiled_display = Self::load(&self.config).unwrap();
         if let Some(tile) = TiledDisplayPlugin::select_tile(&tiled_display, &self.identity) {
+            app.insert_resource(MachineIdentity(self.identity.clone()));
             app.insert_resource(tile);
         };
         // Load tiled display and hostname once, store as resource for easy access.
         app.insert_resource(tiled_display)
             .add_systems(Startup, tiled_window_start_system)
-            .add_systems(PreUpdate, (tiled_camera_hook_system, tiled_ui_hook_system));
+            .add_systems(PreUpdate, (tiled_camera_hook_system, tiled_ui_hook_system))
+            .add_systems(Update, capture_trigger_system);
 
         // Wire synchronization backend.
         if let Some(sync) = self.select_sync() {
+            // Shared by every barrier system this backend registers (both
+            // worlds), so a present-fence failure can still reach a graceful
+            // `AppExit` instead of a destructor-skipping `process::exit`.
+            let sync_failed = SyncFailed::default();
+            app.insert_resource(sync_failed.clone());
             sync.setup(app);
+            if self.present_barrier {
+                if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+                    render_app.insert_resource(sync_failed);
+                    sync.setup_present_barrier(render_app);
+                }
+            }
         }
     }
 }
@@ -205,31 +300,58 @@ fn tiled_window_start_system(
     let position = IVec2::new(tile.window_left as i32, tile.window_top as i32);
     window.position = WindowPosition::At(position);
     window.resolution = WindowResolution::new(tile.window_width as f32, tile.window_height as f32)
-        .with_scale_factor_override(1.0);
+        .with_scale_factor_override(tile.scale_factor());
 }
 
 /// Sets `SubCameraView` for all cameras.
+///
+/// Starts from the bezel-expanded virtual canvas (see `virtual_size`/
+/// `virtual_offset`), so a shape spanning two monitors skips the pixels
+/// hidden behind the bezel instead of compressing into the gap.
+///
+/// HiDPI tiles need no adjustment here: `SubCameraView` only ever uses
+/// `offset`/`size` through their ratio to `full_size` to crop the viewport,
+/// so scaling all three fields by the same per-tile factor would leave those
+/// ratios (and thus the rendered crop) unchanged. The actual HiDPI fix is
+/// `.with_scale_factor_override(tile.scale_factor())` in
+/// `tiled_window_start_system`, which makes the window's physical
+/// framebuffer bigger while `SubCameraView` keeps cropping the same logical
+/// region of the wall.
 fn tiled_camera_hook_system(
     mut cameras: Query<&mut Camera, Added<Camera>>,
     tiled_display: Res<TiledDisplay>,
     tile: Res<Tile>,
 ) {
+    let full_size = tiled_display.virtual_size();
+    let offset = tiled_display.virtual_offset(&tile);
+    let size = tile.size();
     for mut camera in cameras.iter_mut() {
         camera.sub_camera_view = Some(SubCameraView {
-            full_size: tiled_display.size(),
-            offset: tile.offset(),
-            size: tile.size(),
+            full_size,
+            offset,
+            size,
         });
     }
 }
 
 /// Shifts all UI root nodes.
+///
+/// `tile.offset()` is on the wall's raw pixel grid -- the same units as
+/// `window_width`/`window_height`, the literal args to `WindowResolution::new`
+/// in `tiled_window_start_system`. Bevy UI's `Val::Px`, however, is a logical
+/// pixel unit, and `with_scale_factor_override` changes the conversion Bevy
+/// uses between that logical unit and the window's physical pixels away from
+/// 1:1 for any tile with a `scale_factor` other than `1.0`. So the offset must
+/// be divided by `scale_factor` to land back in logical units before being
+/// subtracted from a `Val::Px`. (Unlike `tiled_camera_hook_system`'s
+/// `SubCameraView`, which only ever consumes `offset`/`size` through a ratio
+/// that scaling cancels out, a bare subtraction here does not cancel.)
 fn tiled_ui_hook_system(
     mut root_nodes: Query<&mut Node, (Added<Node>, Without<ChildOf>)>,
     tile: Res<Tile>,
 ) {
     //XXX: this approach is quite hacky but works for now.
-    let offset = tile.offset();
+    let offset = tile.offset() / tile.scale_factor();
     for mut root_node in root_nodes.iter_mut() {
         if root_node.position_type == PositionType::Absolute {
             if let Val::Px(left) = root_node.left {
@@ -242,6 +364,183 @@ fn tiled_ui_hook_system(
     }
 }
 
+/// Reads back this rank's own window framebuffer on [`CAPTURE_KEY`] so the
+/// whole wall can be reassembled into a single image.
+///
+/// `capture_gather_and_save` is an MPI *collective* operation: every rank in
+/// the communicator must call it in the same frame or the gather hangs
+/// forever. Only rank 0's keyboard ever sees the keypress, so under the
+/// `mpi` feature this broadcasts rank 0's `just_pressed` every frame to keep
+/// every rank deciding in lockstep; without MPI each rank only ever writes
+/// its own tile, so no fan-out is needed.
+#[cfg(feature = "mpi")]
+fn capture_trigger_system(keys: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    use mpi::topology::SimpleCommunicator;
+    use mpi::traits::*;
+
+    let world = SimpleCommunicator::world();
+    let root_rank = 0;
+    let root = world.process_at_rank(root_rank);
+    let mut requested = if world.rank() == root_rank {
+        keys.just_pressed(CAPTURE_KEY) as u8
+    } else {
+        0u8
+    };
+    root.broadcast_into(&mut requested);
+
+    if requested != 0 {
+        info!("Capturing tile for full-wall screenshot");
+        commands
+            .spawn(Screenshot::primary_window())
+            .observe(capture_captured_system);
+    }
+}
+
+#[cfg(not(feature = "mpi"))]
+fn capture_trigger_system(keys: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    if keys.just_pressed(CAPTURE_KEY) {
+        info!("Capturing tile for full-wall screenshot");
+        commands
+            .spawn(Screenshot::primary_window())
+            .observe(capture_captured_system);
+    }
+}
+
+/// Stitches (or, without MPI, records) this rank's captured tile into the
+/// full-resolution image of the entire `TiledDisplay`.
+fn capture_captured_system(
+    trigger: Trigger<ScreenshotCaptured>,
+    tiled_display: Res<TiledDisplay>,
+    tile: Res<Tile>,
+) {
+    let Some(pixels) = trigger.event().0.data.clone() else {
+        error!("Captured screenshot has no CPU-side pixel data");
+        return;
+    };
+    let full_size = tiled_display.size();
+    let offset = tile.offset();
+    let size = tile.size();
+
+    #[cfg(feature = "mpi")]
+    capture_gather_and_save(full_size, offset, size, pixels);
+    #[cfg(not(feature = "mpi"))]
+    capture_save_tile(&tile.name, offset, size, pixels);
+}
+
+/// Gathers every rank's tile (RGBA8, plus its offset/size) onto rank 0 via
+/// `gather_varcount_into` and writes the stitched `full_size` image there.
+#[cfg(feature = "mpi")]
+fn capture_gather_and_save(full_size: UVec2, offset: Vec2, size: UVec2, pixels: Vec<u8>) {
+    use mpi::datatype::PartitionMut;
+    use mpi::topology::SimpleCommunicator;
+    use mpi::traits::*;
+
+    let world = SimpleCommunicator::world();
+    let root_rank = 0;
+    let root = world.process_at_rank(root_rank);
+    let metadata = [offset.x, offset.y, size.x as f32, size.y as f32];
+
+    if world.rank() == root_rank {
+        let rank_count = world.size() as usize;
+
+        // Gather every rank's pixel byte count, then their pixels themselves.
+        let mut counts = vec![0i32; rank_count];
+        root.gather_into_root(&(pixels.len() as i32), &mut counts[..]);
+        let mut tile_bytes = vec![0u8; counts.iter().sum::<i32>() as usize];
+        {
+            let displs = displacements(&counts);
+            let mut partition = PartitionMut::new(&mut tile_bytes[..], counts.clone(), displs);
+            root.gather_varcount_into(&pixels[..], &mut partition);
+        }
+
+        // Gather each rank's (offset, size) alongside its pixels.
+        let mut all_metadata = vec![[0f32; 4]; rank_count];
+        root.gather_into_root(&metadata, &mut all_metadata[..]);
+
+        let mut canvas = vec![0u8; (full_size.x * full_size.y * 4) as usize];
+        let mut start = 0usize;
+        for (meta, &count) in all_metadata.iter().zip(counts.iter()) {
+            let rank_offset = Vec2::new(meta[0], meta[1]);
+            let rank_size = UVec2::new(meta[2] as u32, meta[3] as u32);
+            let end = start + count as usize;
+            capture_blit(
+                &mut canvas,
+                full_size,
+                rank_offset,
+                rank_size,
+                &tile_bytes[start..end],
+            );
+            start = end;
+        }
+        capture_write_png("capture.png", full_size, &canvas);
+    } else {
+        root.gather_into(&(pixels.len() as i32));
+        root.gather_varcount_into(&pixels[..]);
+        root.gather_into(&metadata);
+    }
+}
+
+#[cfg(feature = "mpi")]
+fn displacements(counts: &[i32]) -> Vec<i32> {
+    let mut total = 0;
+    counts
+        .iter()
+        .map(|&count| {
+            let displ = total;
+            total += count;
+            displ
+        })
+        .collect()
+}
+
+/// Copies one tile's rows into the full-wall canvas at `offset`, clamping any
+/// rows/columns that would overlap or fall outside the canvas.
+#[cfg(feature = "mpi")]
+fn capture_blit(
+    canvas: &mut [u8],
+    full_size: UVec2,
+    offset: Vec2,
+    size: UVec2,
+    tile_pixels: &[u8],
+) {
+    let canvas_row_stride = full_size.x as usize * 4;
+    let tile_row_stride = size.x as usize * 4;
+    let dest_x = offset.x.max(0.0) as usize;
+    let dest_y = offset.y.max(0.0) as usize;
+    let copy_width = tile_row_stride.min(canvas_row_stride.saturating_sub(dest_x * 4));
+
+    for row in 0..size.y as usize {
+        let dest_row = dest_y + row;
+        if dest_row >= full_size.y as usize {
+            break;
+        }
+        let dest_start = dest_row * canvas_row_stride + dest_x * 4;
+        let src_start = row * tile_row_stride;
+        canvas[dest_start..dest_start + copy_width]
+            .copy_from_slice(&tile_pixels[src_start..src_start + copy_width]);
+    }
+}
+
+/// Without MPI there is no way to gather tiles across processes, so each rank
+/// writes its own tile plus a small manifest a companion step can recombine.
+#[cfg(not(feature = "mpi"))]
+fn capture_save_tile(name: &str, offset: Vec2, size: UVec2, pixels: Vec<u8>) {
+    capture_write_png(&format!("capture_{name}.png"), size, &pixels);
+    let manifest = format!(
+        "{{\"name\":\"{name}\",\"offset\":[{},{}],\"size\":[{},{}]}}",
+        offset.x, offset.y, size.x, size.y
+    );
+    if let Err(error) = std::fs::write(format!("capture_{name}.json"), manifest) {
+        error!(error = %error, "Failed to write capture manifest");
+    }
+}
+
+fn capture_write_png(path: &str, size: UVec2, rgba: &[u8]) {
+    if let Err(error) = image::save_buffer(path, rgba, size.x, size.y, image::ColorType::Rgba8) {
+        error!(error = %error, path, "Failed to write capture image");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -260,4 +559,180 @@ mod tests {
         assert_eq!(td.machines.first().unwrap().identity, "keshiki01");
         assert_eq!(td.machines.last().unwrap().identity, "keshiki20");
     }
+
+    #[test]
+    fn tiled_ui_hook_system_converts_offset_to_logical_pixels() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut tile = bezel_tile("hidpi", 100, 50, 0, 0, 0, 0);
+        tile.scale_factor = Some(2.0);
+
+        let mut world = World::new();
+        world.insert_resource(tile);
+        let node = world
+            .spawn(Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::Px(20.0),
+                ..default()
+            })
+            .id();
+
+        world.run_system_once(tiled_ui_hook_system).unwrap();
+
+        let node = world.get::<Node>(node).unwrap();
+        // offset (100, 50) is on the physical pixel grid; at scale_factor 2.0
+        // that's (50, 25) logical, which is what a `Val::Px` subtraction must use.
+        assert_eq!(node.left, Val::Px(10.0 - 50.0));
+        assert_eq!(node.top, Val::Px(20.0 - 25.0));
+    }
+
+    fn bezel_tile(
+        name: &str,
+        left_offset: i32,
+        top_offset: i32,
+        bezel_left: i32,
+        bezel_right: i32,
+        bezel_top: i32,
+        bezel_bottom: i32,
+    ) -> Tile {
+        Tile {
+            name: name.into(),
+            stereo_channel: StereoChannel::Left,
+            left_offset,
+            top_offset,
+            window_left: left_offset,
+            window_top: top_offset,
+            window_width: 100,
+            window_height: 100,
+            scale_factor: None,
+            bezel_left,
+            bezel_right,
+            bezel_top,
+            bezel_bottom,
+        }
+    }
+
+    /// A synthetic 2x2 wall (100x100 tiles) with a different, non-zero bezel
+    /// on every side across the four tiles.
+    fn bezel_grid() -> TiledDisplay {
+        TiledDisplay {
+            machines: vec![
+                Machine {
+                    identity: "top-left".into(),
+                    tiles: vec![bezel_tile("top-left", 0, 0, 1, 2, 3, 4)],
+                },
+                Machine {
+                    identity: "top-right".into(),
+                    tiles: vec![bezel_tile("top-right", 100, 0, 5, 6, 0, 0)],
+                },
+                Machine {
+                    identity: "bottom-left".into(),
+                    tiles: vec![bezel_tile("bottom-left", 0, 100, 0, 0, 7, 8)],
+                },
+                Machine {
+                    identity: "bottom-right".into(),
+                    tiles: vec![bezel_tile("bottom-right", 100, 100, 0, 0, 0, 0)],
+                },
+            ],
+            name: "BezelGrid".into(),
+            width: 200,
+            height: 200,
+        }
+    }
+
+    #[test]
+    fn virtual_size_adds_accumulated_bezels() {
+        let td = bezel_grid();
+
+        // Top row bezels (1+2) + (5+6) = 14 wider; left column bezels
+        // (3+4) + (7+8) = 22 taller.
+        assert_eq!(td.virtual_size(), UVec2::new(214, 222));
+    }
+
+    #[test]
+    fn virtual_offset_accounts_for_upstream_tiles_bezels() {
+        let td = bezel_grid();
+        let tile = |name: &str| {
+            &td.machines
+                .iter()
+                .find(|m| m.identity == name)
+                .unwrap()
+                .tiles[0]
+        };
+
+        assert_eq!(td.virtual_offset(tile("top-left")), Vec2::new(1.0, 3.0));
+        assert_eq!(td.virtual_offset(tile("top-right")), Vec2::new(108.0, 0.0));
+        assert_eq!(
+            td.virtual_offset(tile("bottom-left")),
+            Vec2::new(0.0, 114.0)
+        );
+        assert_eq!(
+            td.virtual_offset(tile("bottom-right")),
+            Vec2::new(100.0, 100.0)
+        );
+    }
+
+    #[cfg(feature = "mpi")]
+    #[test]
+    fn displacements_returns_exclusive_prefix_sums() {
+        assert_eq!(displacements(&[4, 2, 6]), vec![0, 4, 6]);
+        assert_eq!(displacements(&[]), Vec::<i32>::new());
+        assert_eq!(displacements(&[0, 3]), vec![0, 0]);
+    }
+
+    #[cfg(feature = "mpi")]
+    #[test]
+    fn capture_blit_copies_tile_into_canvas_at_offset() {
+        // 4x4 canvas, blit a fully in-bounds 2x2 tile at (1,1).
+        let full_size = UVec2::new(4, 4);
+        let mut canvas = vec![0u8; (full_size.x * full_size.y * 4) as usize];
+        let tile_pixels: Vec<u8> = (0..2 * 2 * 4).collect();
+
+        capture_blit(
+            &mut canvas,
+            full_size,
+            Vec2::new(1.0, 1.0),
+            UVec2::new(2, 2),
+            &tile_pixels,
+        );
+
+        let row_stride = full_size.x as usize * 4;
+        let pixel_at = |x: usize, y: usize| {
+            canvas[y * row_stride + x * 4..y * row_stride + x * 4 + 4].to_vec()
+        };
+        assert_eq!(pixel_at(1, 1), tile_pixels[0..4]);
+        assert_eq!(pixel_at(2, 1), tile_pixels[4..8]);
+        assert_eq!(pixel_at(1, 2), tile_pixels[8..12]);
+        assert_eq!(pixel_at(2, 2), tile_pixels[12..16]);
+        // Pixels outside the blit stay zeroed.
+        assert_eq!(pixel_at(0, 0), vec![0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "mpi")]
+    #[test]
+    fn capture_blit_clamps_rows_and_columns_overflowing_the_canvas() {
+        // 2x2 canvas; offset + size extends two rows/columns past the right
+        // and bottom edges.
+        let full_size = UVec2::new(2, 2);
+        let mut canvas = vec![0u8; (full_size.x * full_size.y * 4) as usize];
+        let tile_pixels: Vec<u8> = (0..3 * 3 * 4).collect();
+
+        // Must not panic despite offset + size exceeding full_size.
+        capture_blit(
+            &mut canvas,
+            full_size,
+            Vec2::new(1.0, 1.0),
+            UVec2::new(3, 3),
+            &tile_pixels,
+        );
+
+        let row_stride = full_size.x as usize * 4;
+        let pixel_at = |x: usize, y: usize| {
+            canvas[y * row_stride + x * 4..y * row_stride + x * 4 + 4].to_vec()
+        };
+        // Only the tile's top-left texel lands inside the canvas.
+        assert_eq!(pixel_at(1, 1), tile_pixels[0..4]);
+        assert_eq!(pixel_at(0, 0), vec![0, 0, 0, 0]);
+    }
 }